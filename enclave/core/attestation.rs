@@ -1,30 +1,222 @@
 //! Simulated attestation support for the demo enclave.
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime};
+
 use rand::RngCore;
+use secp256k1::ecdsa::{RecoverableSignature, RecoveryId};
+use secp256k1::{Message, PublicKey, Secp256k1};
 use sha2::{Digest, Sha256};
 
+use super::enclave::Enclave;
+use super::secret::SecretKeyData;
+
 /// A mock attestation quote consisting of measurement and nonce.
 pub struct Quote {
     pub mrenclave: String,
     pub signer: String,
     pub nonce: String,
+    /// Recoverable ECDSA signature as hex `r[32] || s[32] || v[1]`.
     pub signature: String,
 }
 
 impl Quote {
-    pub fn new(mrenclave: &str, signer: &str) -> Self {
+    /// Produce a signed quote binding the enclave's measurement to its
+    /// attestation key. The signature is a recoverable secp256k1 ECDSA
+    /// signature over `SHA256(mrenclave || signer || nonce)`.
+    pub fn new(enclave: &Enclave) -> Self {
         let mut nonce_bytes = [0u8; 16];
         rand::thread_rng().fill_bytes(&mut nonce_bytes);
         let nonce = hex::encode(nonce_bytes);
-        let mut hasher = Sha256::new();
-        hasher.update(mrenclave.as_bytes());
-        hasher.update(signer.as_bytes());
-        hasher.update(&nonce_bytes);
-        let signature = format!("{:x}", hasher.finalize());
+
+        let digest = message_digest(&enclave.mrenclave, &enclave.signer, &nonce);
+        let signature = sign(enclave.secret_key(), &digest);
+
+        Self {
+            mrenclave: enclave.mrenclave.clone(),
+            signer: enclave.signer.clone(),
+            nonce,
+            signature,
+        }
+    }
+
+    /// Respond to a verifier's challenge by signing over the verifier-chosen
+    /// nonce instead of a self-generated one, which is what lets the verifier
+    /// guarantee freshness.
+    pub fn respond(enclave: &Enclave, challenge: &Challenge) -> Self {
+        let nonce = challenge.nonce.clone();
+        let digest = message_digest(&enclave.mrenclave, &enclave.signer, &nonce);
+        let signature = sign(enclave.secret_key(), &digest);
+
         Self {
-            mrenclave: mrenclave.to_string(),
-            signer: signer.to_string(),
+            mrenclave: enclave.mrenclave.clone(),
+            signer: enclave.signer.clone(),
             nonce,
             signature,
         }
     }
+
+    /// Produce a quote whose signed digest commits to a transport identity.
+    ///
+    /// The channel binding (e.g. the hash of the certificate's public key) is
+    /// folded into the nonce, so a verifier can confirm the quote was issued
+    /// for *this* key and reject an extension lifted onto a different cert.
+    pub fn bound_to(enclave: &Enclave, channel_binding: &[u8]) -> Self {
+        let nonce = channel_binding_nonce(channel_binding);
+        let digest = message_digest(&enclave.mrenclave, &enclave.signer, &nonce);
+        let signature = sign(enclave.secret_key(), &digest);
+
+        Self {
+            mrenclave: enclave.mrenclave.clone(),
+            signer: enclave.signer.clone(),
+            nonce,
+            signature,
+        }
+    }
+
+    /// Verify the quote against the signer public key the relying party
+    /// expects, recovering the signer from the signature and confirming it
+    /// matches.
+    pub fn verify(&self, expected_signer_pubkey: &PublicKey) -> bool {
+        match self.try_recover_signer() {
+            Some(recovered) => recovered == *expected_signer_pubkey,
+            None => false,
+        }
+    }
+
+    /// Reconstruct the public key that produced this quote's signature.
+    ///
+    /// Panics if the stored signature is malformed; use [`Quote::verify`]
+    /// when the signature may be untrusted.
+    pub fn recover_signer(&self) -> PublicKey {
+        self.try_recover_signer()
+            .expect("quote signature should be a valid recoverable signature")
+    }
+
+    pub(crate) fn try_recover_signer(&self) -> Option<PublicKey> {
+        let raw = hex::decode(&self.signature).ok()?;
+        if raw.len() != 65 {
+            return None;
+        }
+        let compact: [u8; 64] = raw[..64].try_into().ok()?;
+        reject_non_canonical(&compact)?;
+        let recid = RecoveryId::from_i32(raw[64] as i32).ok()?;
+        let sig = RecoverableSignature::from_compact(&compact, recid).ok()?;
+
+        let digest = message_digest(&self.mrenclave, &self.signer, &self.nonce);
+        let msg = Message::from_digest(digest);
+        Secp256k1::new().recover_ecdsa(&msg, &sig).ok()
+    }
+}
+
+/// A verifier-issued challenge pinning a fresh nonce and an expiry.
+#[derive(Clone)]
+pub struct Challenge {
+    pub nonce: String,
+    pub expires_at: SystemTime,
+}
+
+impl Challenge {
+    /// Mint a challenge with a random nonce valid for `ttl`.
+    pub fn new(ttl: Duration) -> Self {
+        let mut nonce_bytes = [0u8; 16];
+        rand::thread_rng().fill_bytes(&mut nonce_bytes);
+        Self {
+            nonce: hex::encode(nonce_bytes),
+            expires_at: SystemTime::now() + ttl,
+        }
+    }
+
+    /// Whether the challenge window has elapsed.
+    pub fn is_expired(&self) -> bool {
+        SystemTime::now() >= self.expires_at
+    }
+}
+
+/// Check that a quote answers a specific challenge: the nonce must match, the
+/// challenge must be unexpired, and the signature must verify against the
+/// signer key the verifier expects.
+pub fn verify_response(quote: &Quote, challenge: &Challenge, expected_signer: &PublicKey) -> bool {
+    if challenge.is_expired() {
+        return false;
+    }
+    if quote.nonce != challenge.nonce {
+        return false;
+    }
+    quote.verify(expected_signer)
+}
+
+/// Tracks outstanding challenges so each can be answered at most once.
+#[derive(Default)]
+pub struct ChallengeVerifier {
+    outstanding: HashMap<String, Challenge>,
+}
+
+impl ChallengeVerifier {
+    /// Create an empty verifier.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Issue and record a fresh challenge valid for `ttl`.
+    pub fn issue(&mut self, ttl: Duration) -> Challenge {
+        let challenge = Challenge::new(ttl);
+        self.outstanding
+            .insert(challenge.nonce.clone(), challenge.clone());
+        challenge
+    }
+
+    /// Verify a quote against an outstanding challenge and the expected signer,
+    /// consuming the challenge on success so the same one cannot be replayed.
+    pub fn verify_response(&mut self, quote: &Quote, expected_signer: &PublicKey) -> bool {
+        let Some(challenge) = self.outstanding.get(&quote.nonce) else {
+            return false;
+        };
+        if !verify_response(quote, challenge, expected_signer) {
+            return false;
+        }
+        self.outstanding.remove(&quote.nonce);
+        true
+    }
+}
+
+/// Derive the nonce that commits a quote to a transport identity: the hex
+/// `SHA256` of the channel binding (typically the peer's SPKI).
+pub(crate) fn channel_binding_nonce(channel_binding: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(channel_binding);
+    hex::encode(hasher.finalize())
+}
+
+/// Compute `d = SHA256(mrenclave || signer || nonce)`.
+pub(crate) fn message_digest(mrenclave: &str, signer: &str, nonce: &str) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(mrenclave.as_bytes());
+    hasher.update(signer.as_bytes());
+    hasher.update(nonce.as_bytes());
+    hasher.finalize().into()
+}
+
+/// Sign a digest with the enclave secret key, returning hex
+/// `r[32] || s[32] || v[1]`. secp256k1 always emits low-S signatures, so the
+/// result is canonical and non-malleable.
+pub(crate) fn sign(secret_key: &SecretKeyData, digest: &[u8; 32]) -> String {
+    let sk = secp256k1::SecretKey::from_slice(secret_key.expose())
+        .expect("enclave secret key is a valid secp256k1 scalar");
+    let msg = Message::from_digest(*digest);
+    let sig = Secp256k1::new().sign_ecdsa_recoverable(&msg, &sk);
+    let (recid, compact) = sig.serialize_compact();
+
+    let mut out = Vec::with_capacity(65);
+    out.extend_from_slice(&compact);
+    out.push(recid.to_i32() as u8);
+    hex::encode(out)
+}
+
+/// Reject signatures whose `r` or `s` scalar is zero or `>=` the curve
+/// order. `SecretKey::from_slice` accepts exactly the valid scalar range
+/// `1..n`, so we reuse it as a range check on each half.
+fn reject_non_canonical(compact: &[u8; 64]) -> Option<()> {
+    secp256k1::SecretKey::from_slice(&compact[..32]).ok()?;
+    secp256k1::SecretKey::from_slice(&compact[32..]).ok()?;
+    Some(())
 }
@@ -0,0 +1,200 @@
+//! Bind a [`Quote`] to a transport identity via a self-signed certificate.
+//!
+//! The enclave issues an ephemeral X.509 certificate carrying its quote in a
+//! custom extension; a relying party parses the extension, checks the quote,
+//! confirms the measurement is allow-listed, and caches the accepted config
+//! keyed by measurement so repeat connections skip re-verification.
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use rcgen::{CertificateParams, CustomExtension, KeyPair};
+use secp256k1::PublicKey;
+use x509_parser::prelude::*;
+
+use super::attestation::{channel_binding_nonce, Quote};
+use super::enclave::Enclave;
+
+/// Private-enterprise OID under which the quote extension is carried.
+const QUOTE_OID: &[u64] = &[1, 3, 6, 1, 4, 1, 99999, 1];
+/// Dotted form of [`QUOTE_OID`], used to match the parsed extension.
+const QUOTE_OID_DOTTED: &str = "1.3.6.1.4.1.99999.1";
+
+/// A hex enclave measurement, used as the cache key.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Mrenclave(pub String);
+
+/// An ephemeral certificate with its private key, both DER-encoded.
+pub struct AttestedCert {
+    pub certificate_der: Vec<u8>,
+    pub private_key_der: Vec<u8>,
+}
+
+/// Issue a self-signed certificate for `enclave` with a fresh ephemeral key
+/// and the enclave's quote embedded as a custom extension.
+pub fn issue_attested_cert(enclave: &Enclave) -> AttestedCert {
+    let key_pair = KeyPair::generate().expect("ephemeral key generation should not fail");
+
+    // Bind the quote to this cert's public key so the extension cannot be
+    // lifted onto a certificate generated with a different private key.
+    let quote = Quote::bound_to(enclave, &key_pair.public_key_der());
+
+    let mut params =
+        CertificateParams::new(vec!["enclave.local".to_string()]).expect("valid subject name");
+    params
+        .custom_extensions
+        .push(CustomExtension::from_oid_content(
+            QUOTE_OID,
+            encode_quote(&quote),
+        ));
+    let cert = params
+        .self_signed(&key_pair)
+        .expect("self-signing an ephemeral cert should not fail");
+
+    AttestedCert {
+        certificate_der: cert.der().to_vec(),
+        private_key_der: key_pair.serialize_der(),
+    }
+}
+
+/// An accepted peer configuration, returned (and cached) on successful
+/// verification.
+pub struct Config {
+    pub mrenclave: Mrenclave,
+    /// Pinned signer public key the quote was verified against.
+    pub signer: PublicKey,
+    /// The attested certificate this config was derived from.
+    pub certificate_der: Vec<u8>,
+}
+
+/// Reasons a peer certificate is rejected.
+#[derive(Debug, PartialEq, Eq)]
+pub enum VerifyError {
+    /// The certificate or its quote extension could not be parsed.
+    Malformed,
+    /// The quote extension was absent.
+    MissingQuote,
+    /// The quote did not verify against the pinned signer for its measurement.
+    BadSignature,
+    /// The measurement is not in the allow-list.
+    MrenclaveNotAllowed,
+    /// The quote is not bound to the presented certificate's public key.
+    ChannelMismatch,
+}
+
+/// Relying-party verifier with a per-measurement accepted-config cache.
+pub struct Verifier {
+    /// Measurements the verifier trusts, each pinned to its expected signer.
+    allowed: HashMap<Mrenclave, PublicKey>,
+    cache: HashMap<Mrenclave, Arc<Config>>,
+}
+
+impl Verifier {
+    /// Build a verifier that accepts only the given measurements, each pinned
+    /// to the signer public key expected to have produced its quote.
+    pub fn new(allowed: impl IntoIterator<Item = (Mrenclave, PublicKey)>) -> Self {
+        Self {
+            allowed: allowed.into_iter().collect(),
+            cache: HashMap::new(),
+        }
+    }
+
+    /// Verify a peer's attested certificate.
+    ///
+    /// On success returns the accepted [`Config`]. A cached entry is reused
+    /// only when the peer presents the same certificate; a rotated cert
+    /// invalidates the entry and forces re-verification.
+    pub fn verify_peer(&mut self, certificate_der: &[u8]) -> Result<Arc<Config>, VerifyError> {
+        let (quote, spki_der) = parse_quote(certificate_der)?;
+        let mrenclave = Mrenclave(quote.mrenclave.clone());
+
+        if let Some(cached) = self.cache.get(&mrenclave) {
+            if cached.certificate_der == certificate_der {
+                return Ok(Arc::clone(cached));
+            }
+            // Peer rotated its attested cert; drop the stale entry.
+            self.cache.remove(&mrenclave);
+        }
+
+        let expected = *self
+            .allowed
+            .get(&mrenclave)
+            .ok_or(VerifyError::MrenclaveNotAllowed)?;
+
+        // The quote must verify against the signer we pinned, not against a
+        // key recovered from its own signature.
+        if !quote.verify(&expected) {
+            return Err(VerifyError::BadSignature);
+        }
+
+        // The quote must be bound to the public key actually presented in the
+        // certificate, defeating extension-lifting.
+        if quote.nonce != channel_binding_nonce(spki_der) {
+            return Err(VerifyError::ChannelMismatch);
+        }
+
+        let config = Arc::new(Config {
+            mrenclave: mrenclave.clone(),
+            signer: expected,
+            certificate_der: certificate_der.to_vec(),
+        });
+        self.cache.insert(mrenclave, Arc::clone(&config));
+        Ok(config)
+    }
+}
+
+/// Serialize a quote's fields as length-prefixed UTF-8 blobs.
+fn encode_quote(quote: &Quote) -> Vec<u8> {
+    let mut out = Vec::new();
+    for field in [
+        &quote.mrenclave,
+        &quote.signer,
+        &quote.nonce,
+        &quote.signature,
+    ] {
+        let bytes = field.as_bytes();
+        out.extend_from_slice(&(bytes.len() as u16).to_be_bytes());
+        out.extend_from_slice(bytes);
+    }
+    out
+}
+
+/// Parse the quote extension and the subject public key out of a DER
+/// certificate, returning both so the caller can check the channel binding.
+fn parse_quote(certificate_der: &[u8]) -> Result<(Quote, &[u8]), VerifyError> {
+    let (_, cert) =
+        X509Certificate::from_der(certificate_der).map_err(|_| VerifyError::Malformed)?;
+    let ext = cert
+        .extensions()
+        .iter()
+        .find(|e| e.oid.to_id_string() == QUOTE_OID_DOTTED)
+        .ok_or(VerifyError::MissingQuote)?;
+    let quote = decode_quote(ext.value).ok_or(VerifyError::Malformed)?;
+    Ok((quote, cert.public_key().raw))
+}
+
+/// Inverse of [`encode_quote`].
+fn decode_quote(mut buf: &[u8]) -> Option<Quote> {
+    let mut fields = Vec::with_capacity(4);
+    for _ in 0..4 {
+        if buf.len() < 2 {
+            return None;
+        }
+        let len = u16::from_be_bytes([buf[0], buf[1]]) as usize;
+        buf = &buf[2..];
+        if buf.len() < len {
+            return None;
+        }
+        fields.push(String::from_utf8(buf[..len].to_vec()).ok()?);
+        buf = &buf[len..];
+    }
+    if !buf.is_empty() {
+        return None;
+    }
+    let mut it = fields.into_iter();
+    Some(Quote {
+        mrenclave: it.next()?,
+        signer: it.next()?,
+        nonce: it.next()?,
+        signature: it.next()?,
+    })
+}
@@ -1,14 +1,21 @@
 //! SGX-style enclave simulation written in Rust for demonstration.
 //! The code is intentionally simple and does not rely on SGX hardware.
 
+use secp256k1::{PublicKey, Secp256k1};
 use sha2::{Digest, Sha256};
 
+use super::secret::SecretKeyData;
+use super::shielded::{Guard, Shielded};
+
 /// Represents a loaded enclave.
 pub struct Enclave {
     pub name: String,
     pub signer: String,
     pub mrenclave: String,
-    pages: Vec<Vec<u8>>,
+    /// secp256k1 attestation keypair bound to this enclave instance.
+    secret_key: SecretKeyData,
+    public_key: PublicKey,
+    pages: Vec<Shielded>,
 }
 
 impl Enclave {
@@ -22,14 +29,35 @@ impl Enclave {
         hasher.update(signer.as_bytes());
         let mrenclave = format!("{:x}", hasher.finalize());
 
+        let secp = Secp256k1::new();
+        let (secret_key, public_key) = secp.generate_keypair(&mut rand::thread_rng());
+
         Self {
             name: name.to_string(),
             signer: signer.to_string(),
             mrenclave,
-            pages: segments.to_vec(),
+            secret_key: SecretKeyData::new(secret_key.secret_bytes()),
+            public_key,
+            pages: segments.iter().map(|seg| Shielded::new(seg)).collect(),
         }
     }
 
+    /// Read a loaded page, returning a guard over its decrypted contents. The
+    /// page is re-encrypted in memory as soon as the guard is dropped.
+    pub fn read_page(&self, i: usize) -> Guard<'_> {
+        self.pages[i].unshield()
+    }
+
+    /// The enclave's attestation signing key.
+    pub(crate) fn secret_key(&self) -> &SecretKeyData {
+        &self.secret_key
+    }
+
+    /// The public half of the enclave's attestation keypair.
+    pub fn public_key(&self) -> &PublicKey {
+        &self.public_key
+    }
+
     /// Perform an ECALL into the enclave.
     pub fn ecall(&self, name: &str) -> String {
         format!("ECALL {} executed inside {}", name, self.name)
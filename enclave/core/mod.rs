@@ -0,0 +1,7 @@
+//! Core primitives for the demo enclave.
+pub mod attestation;
+pub mod attested_tls;
+pub mod enclave;
+pub mod sealed_storage;
+pub mod secret;
+pub mod shielded;
@@ -1,31 +1,97 @@
 //! Demonstration of sealed storage.
-use sha2::{Digest, Sha256};
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use hkdf::Hkdf;
+use rand::RngCore;
+use sha2::Sha256;
 
-/// Seal data to a pseudo-key derived from identity and measurement.
+use super::secret::SecretKeyData;
+
+/// Context string mixed into the HKDF `info` so keys derived here cannot
+/// collide with keys derived for other purposes.
+const SEAL_INFO: &[u8] = b"enclave-core/sealed-storage/v1";
+
+/// Length of the AES-256-GCM nonce, in bytes.
+const NONCE_LEN: usize = 12;
+/// Length of the AES-256-GCM authentication tag, in bytes.
+const TAG_LEN: usize = 16;
+
+/// Errors returned when sealed data cannot be recovered.
+#[derive(Debug, PartialEq, Eq)]
+pub enum SealError {
+    /// The hex blob was not valid hexadecimal.
+    InvalidHex,
+    /// The blob was shorter than a nonce plus tag and cannot be a sealed value.
+    Truncated,
+    /// Authentication failed: the ciphertext, tag, or AAD did not match.
+    AuthFailure,
+}
+
+/// Derive the per-measurement sealing key from `identity || mrenclave`.
+fn derive_key(identity: &str, mrenclave: &str) -> SecretKeyData {
+    let mut ikm = Vec::with_capacity(identity.len() + mrenclave.len());
+    ikm.extend_from_slice(identity.as_bytes());
+    ikm.extend_from_slice(mrenclave.as_bytes());
+
+    let hk = Hkdf::<Sha256>::new(None, &ikm);
+    let mut key = [0u8; 32];
+    hk.expand(SEAL_INFO, &mut key)
+        .expect("32 is a valid HKDF-SHA256 output length");
+    SecretKeyData::new(key)
+}
+
+/// Seal data under a key derived from identity and measurement.
+///
+/// The `mrenclave` is bound as additional authenticated data, so a blob
+/// sealed under one measurement cannot be unsealed under another. The output
+/// is hex-encoded `nonce (12 bytes) || ciphertext || tag (16 bytes)`.
 pub fn seal(identity: &str, mrenclave: &str, data: &[u8]) -> String {
-    let mut key_hasher = Sha256::new();
-    key_hasher.update(identity.as_bytes());
-    key_hasher.update(mrenclave.as_bytes());
-    let key = key_hasher.finalize();
-
-    let mut cipher = Vec::with_capacity(data.len());
-    for (i, byte) in data.iter().enumerate() {
-        cipher.push(byte ^ key[i % key.len()]);
-    }
-    hex::encode(cipher)
+    let key = derive_key(identity, mrenclave);
+    let cipher = Aes256Gcm::new(key.expose().into());
+
+    let mut nonce_bytes = [0u8; NONCE_LEN];
+    rand::thread_rng().fill_bytes(&mut nonce_bytes);
+    let nonce = Nonce::from_slice(&nonce_bytes);
+
+    let ciphertext = cipher
+        .encrypt(
+            nonce,
+            Payload {
+                msg: data,
+                aad: mrenclave.as_bytes(),
+            },
+        )
+        .expect("AES-256-GCM encryption is infallible for in-memory buffers");
+
+    let mut out = Vec::with_capacity(NONCE_LEN + ciphertext.len());
+    out.extend_from_slice(&nonce_bytes);
+    out.extend_from_slice(&ciphertext);
+    hex::encode(out)
 }
 
 /// Unseal data previously sealed with [`seal`].
-pub fn unseal(identity: &str, mrenclave: &str, cipher_hex: &str) -> Vec<u8> {
-    let cipher = hex::decode(cipher_hex).expect("ciphertext should be hex");
-    let mut key_hasher = Sha256::new();
-    key_hasher.update(identity.as_bytes());
-    key_hasher.update(mrenclave.as_bytes());
-    let key = key_hasher.finalize();
+///
+/// Fails on truncated input, a tag mismatch, or a measurement (AAD) mismatch
+/// rather than silently returning garbage.
+pub fn unseal(identity: &str, mrenclave: &str, cipher_hex: &str) -> Result<Vec<u8>, SealError> {
+    let blob = hex::decode(cipher_hex).map_err(|_| SealError::InvalidHex)?;
+    if blob.len() < NONCE_LEN + TAG_LEN {
+        return Err(SealError::Truncated);
+    }
+
+    let (nonce_bytes, ciphertext) = blob.split_at(NONCE_LEN);
+    let nonce = Nonce::from_slice(nonce_bytes);
+
+    let key = derive_key(identity, mrenclave);
+    let cipher = Aes256Gcm::new(key.expose().into());
 
     cipher
-        .iter()
-        .enumerate()
-        .map(|(i, byte)| byte ^ key[i % key.len()])
-        .collect()
+        .decrypt(
+            nonce,
+            Payload {
+                msg: ciphertext,
+                aad: mrenclave.as_bytes(),
+            },
+        )
+        .map_err(|_| SealError::AuthFailure)
 }
@@ -0,0 +1,49 @@
+//! Secret-key material with zero-on-free handling.
+use std::sync::atomic::{compiler_fence, Ordering};
+
+/// Wraps a 256-bit secret (a sealing or signing key) and overwrites it on
+/// drop.
+///
+/// It deliberately implements neither `Copy` nor `Clone`, so key bytes cannot
+/// be silently duplicated across the codebase; callers borrow [`expose`] for
+/// the brief window they need the raw bytes.
+///
+/// [`expose`]: SecretKeyData::expose
+pub struct SecretKeyData([u8; 32]);
+
+impl SecretKeyData {
+    /// Take ownership of the raw key bytes.
+    pub fn new(bytes: [u8; 32]) -> Self {
+        Self(bytes)
+    }
+
+    /// Borrow the raw key bytes. Keep the borrow as short-lived as possible.
+    pub fn expose(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+impl Drop for SecretKeyData {
+    fn drop(&mut self) {
+        for byte in self.0.iter_mut() {
+            unsafe { std::ptr::write_volatile(byte, 0) };
+        }
+        compiler_fence(Ordering::SeqCst);
+    }
+}
+
+/// Compare two byte slices in constant time with respect to their contents.
+///
+/// Returns `false` immediately for a length mismatch, then folds every byte
+/// of equal-length inputs so the running time does not reveal where the first
+/// difference lies. Use this for secrets and authentication tags.
+pub fn ct_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut diff = 0u8;
+    for (x, y) in a.iter().zip(b.iter()) {
+        diff |= x ^ y;
+    }
+    diff == 0
+}
@@ -0,0 +1,122 @@
+//! Encrypt enclave contents at rest in memory.
+//!
+//! A [`Shielded`] buffer keeps its payload encrypted under a key that exists
+//! only while the payload is being encrypted or decrypted. The key is derived
+//! from a large random "prekey" rather than stored directly, so a stray read of the
+//! struct reveals neither the plaintext nor a usable key, and the plaintext
+//! scratch buffer is wiped as soon as the guard is dropped.
+use std::cell::RefCell;
+use std::ops::Deref;
+
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use rand::RngCore;
+use sha2::{Digest, Sha512};
+
+/// Size of the random prekey hashed down to a ChaCha20-Poly1305 key+nonce.
+const PREKEY_LEN: usize = 16 * 1024;
+
+/// A payload kept encrypted in memory except while actively being read.
+pub struct Shielded {
+    prekey: RefCell<Vec<u8>>,
+    ciphertext: RefCell<Vec<u8>>,
+}
+
+impl Shielded {
+    /// Encrypt `payload` under a freshly generated prekey.
+    pub fn new(payload: &[u8]) -> Self {
+        let prekey = random_prekey();
+        let ciphertext = seal_with(&prekey, payload);
+        Self {
+            prekey: RefCell::new(prekey),
+            ciphertext: RefCell::new(ciphertext),
+        }
+    }
+
+    /// Decrypt into a scratch buffer, returning a guard that re-encrypts the
+    /// contents under a fresh key and wipes the scratch on drop.
+    pub fn unshield(&self) -> Guard<'_> {
+        let plaintext = {
+            let prekey = self.prekey.borrow();
+            open_with(&prekey, &self.ciphertext.borrow())
+        };
+        Guard {
+            owner: self,
+            scratch: plaintext,
+        }
+    }
+}
+
+impl Drop for Shielded {
+    fn drop(&mut self) {
+        zero(&mut self.prekey.borrow_mut());
+    }
+}
+
+/// A live, decrypted view of a [`Shielded`] payload.
+///
+/// Dereferences to the plaintext bytes. On drop the plaintext is re-encrypted
+/// under a new prekey and the scratch buffer is zeroed.
+pub struct Guard<'a> {
+    owner: &'a Shielded,
+    scratch: Vec<u8>,
+}
+
+impl Deref for Guard<'_> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.scratch
+    }
+}
+
+impl Drop for Guard<'_> {
+    fn drop(&mut self) {
+        let new_prekey = random_prekey();
+        let new_ciphertext = seal_with(&new_prekey, &self.scratch);
+
+        let mut prekey = self.owner.prekey.borrow_mut();
+        zero(&mut prekey);
+        *prekey = new_prekey;
+        *self.owner.ciphertext.borrow_mut() = new_ciphertext;
+
+        zero(&mut self.scratch);
+    }
+}
+
+fn random_prekey() -> Vec<u8> {
+    let mut prekey = vec![0u8; PREKEY_LEN];
+    rand::thread_rng().fill_bytes(&mut prekey);
+    prekey
+}
+
+/// Derive the ChaCha20-Poly1305 key and nonce from the prekey via SHA-512.
+fn derive(prekey: &[u8]) -> (Key, Nonce) {
+    let digest = Sha512::digest(prekey);
+    let key = Key::clone_from_slice(&digest[..32]);
+    let nonce = Nonce::clone_from_slice(&digest[32..44]);
+    (key, nonce)
+}
+
+fn seal_with(prekey: &[u8], plaintext: &[u8]) -> Vec<u8> {
+    let (key, nonce) = derive(prekey);
+    ChaCha20Poly1305::new(&key)
+        .encrypt(&nonce, plaintext)
+        .expect("ChaCha20-Poly1305 encryption is infallible for in-memory buffers")
+}
+
+fn open_with(prekey: &[u8], ciphertext: &[u8]) -> Vec<u8> {
+    let (key, nonce) = derive(prekey);
+    ChaCha20Poly1305::new(&key)
+        .decrypt(&nonce, ciphertext)
+        .expect("shielded ciphertext is produced internally and always authenticates")
+}
+
+/// Overwrite a buffer with zeros using volatile writes the optimizer may not
+/// elide.
+fn zero(buf: &mut [u8]) {
+    for byte in buf.iter_mut() {
+        unsafe { std::ptr::write_volatile(byte, 0) };
+    }
+    std::sync::atomic::compiler_fence(std::sync::atomic::Ordering::SeqCst);
+}